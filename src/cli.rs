@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::{bail, eyre};
+
+use crate::{downsample::Downsample, precision::Precision};
+
+const USAGE: &str = "\
+Usage: chart-a-tui [OPTIONS]
+
+Options:
+  --window <BARS>  Show only this many of the most recent bars when data is
+                   loaded, until w is pressed or downsampling is on
+  --downsample <METHOD>
+                   Fit all data on screen using lttb, minmax or mean
+  --precision <DIGITS>
+                   Show values with DIGITS decimals, or DIGITSs significant
+                   digits such as 3s (default: auto)
+  --record <FILE>  Record the session as an asciinema cast
+  --deterministic  Produce identical output across runs and machines
+  --read-only      Disable all features which modify data
+  -h, --help       Print this help and exit";
+
+/// Command line arguments of the application.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// How many of the most recent bars to show when data is loaded.
+    pub window: Option<usize>,
+    /// How to reduce the series to fit on screen, instead of scrolling.
+    pub downsample: Option<Downsample>,
+    /// How many digits values are shown with.
+    pub precision: Precision,
+    /// Where to write an asciinema recording of the session, if anywhere.
+    pub record: Option<PathBuf>,
+    /// Avoid anything which varies between runs, such as wall-clock timing.
+    pub deterministic: bool,
+    /// Disable every feature which modifies the loaded data.
+    pub read_only: bool,
+}
+
+impl Args {
+    /// Parse the arguments the process was started with.
+    ///
+    /// Prints the usage and exits when `--help` is given.
+    pub fn parse() -> color_eyre::Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    /// Parse arguments from an iterator, not including the program name.
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> color_eyre::Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--window" => {
+                    let bars = args
+                        .next()
+                        .ok_or_else(|| eyre!("--window requires a number of bars"))?;
+                    match bars.parse() {
+                        Ok(0) | Err(_) => bail!("--window must be a positive number, got {bars}"),
+                        Ok(bars) => parsed.window = Some(bars),
+                    }
+                }
+                "--downsample" => {
+                    let method = args
+                        .next()
+                        .ok_or_else(|| eyre!("--downsample requires a method"))?;
+                    parsed.downsample = Some(method.parse()?);
+                }
+                "--precision" => {
+                    let precision = args
+                        .next()
+                        .ok_or_else(|| eyre!("--precision requires a number of digits"))?;
+                    parsed.precision = precision.parse()?;
+                }
+                "--record" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--record requires a file path"))?;
+                    parsed.record = Some(PathBuf::from(path));
+                }
+                "--deterministic" => parsed.deterministic = true,
+                "--read-only" => parsed.read_only = true,
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => bail!("unknown argument: {arg}\n\n{USAGE}"),
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,69 @@
+use std::io::{self, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Copy `text` to the system clipboard using the OSC 52 escape sequence.
+///
+/// The terminal emulator sets the clipboard itself, so this works over SSH
+/// without any native clipboard on the machine running the app. Inside tmux
+/// the sequence is wrapped in a passthrough so it reaches the outer terminal.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if std::env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()
+}
+
+/// Encode bytes as padded standard base64.
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        // The test vectors from section 10 of RFC 4648.
+        for (input, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(input.as_bytes()), encoded, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn base64_uses_the_whole_alphabet() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded = base64(&bytes);
+        assert_eq!(encoded.len(), 344);
+        assert!(encoded.starts_with("AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8g"));
+        assert!(encoded.ends_with("+/w=="));
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(base64("a,1\nb,2\n".as_bytes()), "YSwxCmIsMgo=");
+    }
+}
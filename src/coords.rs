@@ -0,0 +1,152 @@
+use ratatui::layout::{Position, Rect};
+
+use crate::render::ChartLayout;
+
+/// Maps between terminal cells and data coordinates of a laid out chart.
+///
+/// Everything which needs to know what is drawn where, such as mouse
+/// handling, goes through this so it agrees with the renderer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartCoords {
+    /// The cells the bars are drawn in.
+    plot: Rect,
+    /// The number of bars in view.
+    bars: usize,
+    bar_width: u16,
+    bar_gap: u16,
+    /// The value at the bottom of the plot.
+    min: f64,
+    /// The value drawn as a full height bar.
+    max: f64,
+}
+
+impl ChartCoords {
+    pub fn new(chart: &ChartLayout) -> Self {
+        Self {
+            plot: chart.plot,
+            bars: chart.bars.len(),
+            bar_width: chart.bar_width,
+            bar_gap: chart.bar_gap,
+            min: chart.min,
+            max: chart.max,
+        }
+    }
+
+    /// Is `position` inside the area the bars are drawn in?
+    pub fn contains(&self, position: Position) -> bool {
+        self.plot.contains(position)
+    }
+
+    /// The position in view of the bar drawn in `column`, if any.
+    ///
+    /// Columns in the gap between two bars don't belong to either.
+    pub fn bar_at(&self, column: u16) -> Option<usize> {
+        let offset = column.checked_sub(self.plot.x)?;
+        let slot = self.bar_width + self.bar_gap;
+        let index = usize::from(offset / slot);
+        (offset % slot < self.bar_width && index < self.bars).then_some(index)
+    }
+
+    /// The value at the top edge of `row`, which is as high as a bar reaching that row can be.
+    ///
+    /// Rows above or below the plot are clamped to its top and bottom.
+    pub fn row_to_value(&self, row: u16) -> f64 {
+        let row = row.clamp(self.plot.top(), self.plot.bottom());
+        let height = f64::from(self.plot.height.max(1));
+        self.min + f64::from(self.plot.bottom() - row) / height * (self.max - self.min)
+    }
+
+    /// The row whose top edge is closest to `value`, the inverse of [`Self::row_to_value`].
+    ///
+    /// Values outside the plot are clamped to its top and bottom rows.
+    pub fn value_to_row(&self, value: f64) -> u16 {
+        let height = f64::from(self.plot.height);
+        let rows = ((value - self.min) / (self.max - self.min) * height).round();
+        let rows = rows.clamp(1.0, height.max(1.0)) as u16;
+        self.plot.bottom().saturating_sub(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{precision::Precision, render::Viewport};
+
+    /// Coordinates of a chart whose plot is the 30x9 cells at (1, 1), three bars wide.
+    fn coords(values: &[f64]) -> ChartCoords {
+        let data: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i.to_string(), *value))
+            .collect();
+        let chart = ChartLayout::new(
+            "Test",
+            &data,
+            Rect::new(0, 0, 32, 12),
+            Viewport::default(),
+            None,
+            Precision::Auto,
+        );
+        assert_eq!(chart.plot, Rect::new(1, 1, 30, 9));
+        ChartCoords::new(&chart)
+    }
+
+    #[test]
+    fn bar_at_finds_the_bar_in_a_column() {
+        let coords = coords(&[1.0, 2.0, 4.0]);
+        for (columns, bar) in [(1..10, 0), (11..20, 1), (21..30, 2)] {
+            for column in columns {
+                assert_eq!(coords.bar_at(column), Some(bar), "column {column}");
+            }
+        }
+    }
+
+    #[test]
+    fn bar_at_skips_gaps_and_empty_space() {
+        let coords = coords(&[1.0, 2.0]);
+        // Left of the plot, the gaps after each bar, and where a third bar would be.
+        for column in [0, 10, 20, 21, 25, 29, 30, 31, u16::MAX] {
+            assert_eq!(coords.bar_at(column), None, "column {column}");
+        }
+    }
+
+    #[test]
+    fn row_to_value_spans_the_plot() {
+        let coords = coords(&[1.0, 2.0, 9.0]);
+        assert_eq!(coords.row_to_value(10), 0.0);
+        assert_eq!(coords.row_to_value(7), 3.0);
+        assert_eq!(coords.row_to_value(1), 9.0);
+        // Rows outside the plot are clamped.
+        assert_eq!(coords.row_to_value(0), 9.0);
+        assert_eq!(coords.row_to_value(50), 0.0);
+    }
+
+    #[test]
+    fn value_to_row_inverts_row_to_value() {
+        for values in [[1.0, 2.0, 9.0], [-2.0, 1.0, 6.0], [-5.0, -1.0, -3.0]] {
+            let coords = coords(&values);
+            for row in 1..10 {
+                assert_eq!(coords.value_to_row(coords.row_to_value(row)), row);
+            }
+        }
+    }
+
+    #[test]
+    fn value_to_row_clamps_to_the_plot() {
+        let coords = coords(&[-2.0, 6.0]);
+        assert_eq!(coords.value_to_row(0.0), 8);
+        assert_eq!(coords.value_to_row(100.0), 1);
+        assert_eq!(coords.value_to_row(-100.0), 9);
+    }
+
+    #[test]
+    fn contains_only_the_plot() {
+        let coords = coords(&[1.0]);
+        assert!(coords.contains(Position::new(1, 1)));
+        assert!(coords.contains(Position::new(30, 9)));
+        assert!(!coords.contains(Position::new(0, 1)));
+        assert!(!coords.contains(Position::new(31, 5)));
+        // The row of labels under the plot.
+        assert!(!coords.contains(Position::new(5, 10)));
+    }
+}
@@ -0,0 +1,282 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::eyre::bail;
+
+/// How a series is reduced to fewer bars when it has more than fit on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Downsample {
+    /// Largest-Triangle-Three-Buckets, which keeps the points that shape the series.
+    Lttb,
+    /// The smallest and largest value of each bucket, preserving spikes.
+    MinMax,
+    /// The mean of each bucket, smoothing out noise.
+    Mean,
+}
+
+impl Downsample {
+    /// The method after `method` when cycling through them, `None` meaning no downsampling.
+    pub fn cycle(method: Option<Self>) -> Option<Self> {
+        match method {
+            None => Some(Self::Lttb),
+            Some(Self::Lttb) => Some(Self::MinMax),
+            Some(Self::MinMax) => Some(Self::Mean),
+            Some(Self::Mean) => None,
+        }
+    }
+
+    /// Reduce `data` to at most `target` points.
+    ///
+    /// Data which already fits is returned as is.
+    pub fn apply(self, data: &[(String, f64)], target: usize) -> Vec<(String, f64)> {
+        if data.len() <= target || target == 0 {
+            return data.to_vec();
+        }
+        match self {
+            Self::Lttb => lttb(data, target),
+            Self::MinMax => min_max(data, target),
+            Self::Mean => mean(data, target),
+        }
+    }
+}
+
+impl fmt::Display for Downsample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lttb => "lttb",
+            Self::MinMax => "minmax",
+            Self::Mean => "mean",
+        })
+    }
+}
+
+impl FromStr for Downsample {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lttb" => Ok(Self::Lttb),
+            "minmax" => Ok(Self::MinMax),
+            "mean" => Ok(Self::Mean),
+            _ => bail!("unknown downsampling method {s:?}, expected lttb, minmax or mean"),
+        }
+    }
+}
+
+/// Split `len` points into `buckets` contiguous ranges of near equal size.
+fn buckets(
+    start: usize,
+    len: usize,
+    buckets: usize,
+) -> impl Iterator<Item = std::ops::Range<usize>> {
+    (0..buckets).map(move |i| start + i * len / buckets..start + (i + 1) * len / buckets)
+}
+
+fn lttb(data: &[(String, f64)], target: usize) -> Vec<(String, f64)> {
+    // The first and last points are always kept, so there need to be buckets between them.
+    if target < 3 {
+        return min_max(data, target);
+    }
+    let y = |i: usize| data[i].1;
+    let mut selected = vec![0];
+    let inner: Vec<_> = buckets(1, data.len() - 2, target - 2).collect();
+    for (i, bucket) in inner.iter().enumerate() {
+        let previous = *selected.last().unwrap_or(&0);
+        // The third point of the triangle is the average of the next bucket.
+        let (next_x, next_y) = match inner.get(i + 1) {
+            Some(next) => {
+                let n = next.len() as f64;
+                let x = next.clone().sum::<usize>() as f64 / n;
+                (x, next.clone().map(y).sum::<f64>() / n)
+            }
+            None => ((data.len() - 1) as f64, y(data.len() - 1)),
+        };
+        let area = |j: usize| {
+            let (ax, ay) = (previous as f64, y(previous));
+            ((ax - next_x) * (y(j) - ay) - (ax - j as f64) * (next_y - ay)).abs()
+        };
+        let best = bucket
+            .clone()
+            .max_by(|&a, &b| area(a).total_cmp(&area(b)))
+            .unwrap_or(bucket.start);
+        selected.push(best);
+    }
+    selected.push(data.len() - 1);
+    selected.into_iter().map(|i| data[i].clone()).collect()
+}
+
+fn min_max(data: &[(String, f64)], target: usize) -> Vec<(String, f64)> {
+    // A single bar can only show the peak.
+    if target < 2 {
+        return data
+            .iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .cloned()
+            .into_iter()
+            .collect();
+    }
+    let mut sampled = Vec::with_capacity(target);
+    for bucket in buckets(0, data.len(), target / 2) {
+        let by_value = |&a: &usize, &b: &usize| data[a].1.total_cmp(&data[b].1);
+        let min = bucket.clone().min_by(by_value).unwrap_or(bucket.start);
+        let max = bucket.clone().max_by(by_value).unwrap_or(bucket.start);
+        // Keep the original order so the envelope still reads left to right.
+        let (first, second) = (min.min(max), min.max(max));
+        sampled.push(data[first].clone());
+        if first != second {
+            sampled.push(data[second].clone());
+        }
+    }
+    sampled
+}
+
+fn mean(data: &[(String, f64)], target: usize) -> Vec<(String, f64)> {
+    buckets(0, data.len(), target)
+        .map(|bucket| {
+            let values = &data[bucket.clone()];
+            let sum: f64 = values.iter().map(|(_, value)| value).sum();
+            (data[bucket.start].0.clone(), sum / values.len() as f64)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [Downsample; 3] = [Downsample::Lttb, Downsample::MinMax, Downsample::Mean];
+
+    fn series(values: &[f64]) -> Vec<(String, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i.to_string(), *value))
+            .collect()
+    }
+
+    fn values(data: &[(String, f64)]) -> Vec<f64> {
+        data.iter().map(|(_, value)| *value).collect()
+    }
+
+    fn labels(data: &[(String, f64)]) -> Vec<&str> {
+        data.iter().map(|(label, _)| label.as_str()).collect()
+    }
+
+    #[test]
+    fn buckets_cover_the_range_in_order() {
+        let buckets: Vec<_> = buckets(1, 10, 3).collect();
+        assert_eq!(buckets, [1..4, 4..7, 7..11]);
+        for len in 1..50 {
+            for count in 1..=len {
+                let buckets: Vec<_> = super::buckets(0, len, count).collect();
+                assert_eq!(buckets.len(), count);
+                assert_eq!(buckets[0].start, 0);
+                assert_eq!(buckets[count - 1].end, len);
+                assert!(buckets.windows(2).all(|pair| pair[0].end == pair[1].start));
+                assert!(buckets.iter().all(|bucket| !bucket.is_empty()));
+            }
+        }
+    }
+
+    #[test]
+    fn output_fits_the_target() {
+        let data = series(&(0..100).map(|i| f64::from(i % 7)).collect::<Vec<_>>());
+        for method in METHODS {
+            for target in 1..=100 {
+                let sampled = method.apply(&data, target);
+                assert!(
+                    !sampled.is_empty() && sampled.len() <= target,
+                    "{method} gave {} points for a target of {target}",
+                    sampled.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn data_which_fits_is_unchanged() {
+        let data = series(&[3.0, 1.0, 2.0]);
+        for method in METHODS {
+            assert_eq!(method.apply(&data, 3), data);
+            assert_eq!(method.apply(&data, 10), data);
+            assert_eq!(method.apply(&data, 0), data);
+        }
+    }
+
+    #[test]
+    fn lttb_keeps_the_first_and_last_points() {
+        let data = series(&(0..50).map(|i| f64::from(i * i % 13)).collect::<Vec<_>>());
+        for target in 3..50 {
+            let sampled = Downsample::Lttb.apply(&data, target);
+            assert_eq!(sampled.len(), target);
+            assert_eq!(sampled.first(), data.first());
+            assert_eq!(sampled.last(), data.last());
+            // Points are picked, never made up, and stay in order.
+            let picked: Vec<usize> = labels(&sampled)
+                .iter()
+                .map(|l| l.parse().unwrap())
+                .collect();
+            assert!(picked.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn lttb_keeps_a_spike() {
+        let mut points = vec![1.0; 30];
+        points[17] = 50.0;
+        let sampled = Downsample::Lttb.apply(&series(&points), 5);
+        assert!(values(&sampled).contains(&50.0));
+    }
+
+    #[test]
+    fn min_max_keeps_spikes_and_dips() {
+        let mut points = vec![5.0; 100];
+        points[37] = 100.0;
+        points[81] = -20.0;
+        let sampled = Downsample::MinMax.apply(&series(&points), 10);
+        assert_eq!(sampled.len(), 10);
+        assert!(sampled.contains(&("37".to_string(), 100.0)));
+        assert!(sampled.contains(&("81".to_string(), -20.0)));
+    }
+
+    #[test]
+    fn min_max_keeps_the_order_within_a_bucket() {
+        let sampled = Downsample::MinMax.apply(&series(&[2.0, 9.0, 1.0, 5.0]), 2);
+        assert_eq!(labels(&sampled), ["1", "2"]);
+        assert_eq!(values(&sampled), [9.0, 1.0]);
+    }
+
+    #[test]
+    fn mean_averages_each_bucket() {
+        let sampled = Downsample::Mean.apply(&series(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]), 3);
+        assert_eq!(labels(&sampled), ["0", "2", "4"]);
+        assert_eq!(values(&sampled), [1.5, 3.5, 6.0]);
+    }
+
+    #[test]
+    fn small_targets() {
+        let data = series(&[4.0, 8.0, 2.0, 6.0]);
+        // A single bar shows the peak, or the mean of everything.
+        assert_eq!(Downsample::Lttb.apply(&data, 1), [("1".to_string(), 8.0)]);
+        assert_eq!(Downsample::MinMax.apply(&data, 1), [("1".to_string(), 8.0)]);
+        assert_eq!(Downsample::Mean.apply(&data, 1), [("0".to_string(), 5.0)]);
+        // Two bars show the range, or the mean of each half.
+        assert_eq!(values(&Downsample::Lttb.apply(&data, 2)), [8.0, 2.0]);
+        assert_eq!(values(&Downsample::MinMax.apply(&data, 2)), [8.0, 2.0]);
+        assert_eq!(values(&Downsample::Mean.apply(&data, 2)), [6.0, 4.0]);
+    }
+
+    #[test]
+    fn methods_cycle_back_to_off() {
+        let mut method = None;
+        let mut seen = Vec::new();
+        loop {
+            method = Downsample::cycle(method);
+            let Some(current) = method else { break };
+            seen.push(current);
+        }
+        assert_eq!(seen, METHODS);
+        for method in METHODS {
+            assert_eq!(method.to_string().parse::<Downsample>().unwrap(), method);
+        }
+    }
+}
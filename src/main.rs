@@ -1,16 +1,50 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use std::{io::stdout, path::Path};
+
+use crossterm::{
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute,
+};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Position},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Bar, BarChart, BarGroup, Block, Paragraph},
+    widgets::{Block, Paragraph, Wrap},
     DefaultTerminal, Frame,
 };
 
+use crate::{
+    cli::Args,
+    coords::ChartCoords,
+    downsample::Downsample,
+    precision::Precision,
+    record::Recorder,
+    render::{ChartLayout, Renderer, TerminalRenderer, Viewport},
+    transform::{Pending, Transform},
+};
+
+mod cli;
+mod clipboard;
+mod coords;
+mod downsample;
+mod precision;
+mod record;
+mod render;
+mod transform;
+
 fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
+    let app = App::from_args(Args::parse()?)?;
     let terminal = ratatui::init();
-    let result = App::new().run(terminal);
+    // Pasting is a convenience which legacy Windows consoles don't support, so it can't stop the app.
+    let _ = execute!(stdout(), EnableBracketedPaste);
+    let result = execute!(stdout(), EnableMouseCapture)
+        .map_err(Into::into)
+        .and_then(|()| app.run(terminal));
+    let _ = execute!(stdout(), DisableMouseCapture);
+    let _ = execute!(stdout(), DisableBracketedPaste);
     ratatui::restore();
     result
 }
@@ -21,6 +55,8 @@ pub enum InputMode {
     #[default]
     Normal,
     Editing,
+    /// Entering the constant for a transform of the data.
+    Constant(Pending),
 }
 
 /// The main application which holds the state and logic of the application.
@@ -32,10 +68,30 @@ pub struct App {
     input_mode: InputMode,
     /// The current input for the CSV path.
     input: String,
+    /// The current input for the constant of a transform.
+    constant: String,
     /// The data for the chart.
-    data: Vec<(String, u64)>,
+    data: Vec<(String, f64)>,
+    /// How many of the most recent bars are in view when data is loaded, if limited.
+    window: Option<usize>,
+    /// Which part of the data is in view.
+    viewport: Viewport,
+    /// The chart as it was last drawn, for mapping mouse positions to data.
+    chart: Option<ChartLayout>,
+    /// The row of the level readout, counted up from the bottom of the plot, if it is shown.
+    readout: Option<u16>,
+    /// How the series is reduced to fit on screen, if at all.
+    downsample: Option<Downsample>,
+    /// How many digits values are shown with.
+    precision: Precision,
     /// Error message to display.
     error_message: Option<String>,
+    /// Status message to display, such as the result of the last action.
+    status_message: Option<String>,
+    /// Records every rendered frame when the session is being recorded.
+    recorder: Option<Recorder>,
+    /// Are features which modify the data disabled?
+    read_only: bool,
 }
 
 impl App {
@@ -44,12 +100,37 @@ impl App {
         Self::default()
     }
 
+    /// Construct a new instance of [`App`] configured from the command line.
+    pub fn from_args(args: Args) -> color_eyre::Result<Self> {
+        let mut app = Self {
+            read_only: args.read_only,
+            downsample: args.downsample,
+            precision: args.precision,
+            window: args.window,
+            viewport: Viewport {
+                window: args.window,
+                ..Viewport::default()
+            },
+            ..Self::new()
+        };
+        if let Some(path) = &args.record {
+            app.recorder = Some(Recorder::create(path, args.deterministic)?);
+        }
+        Ok(app)
+    }
+
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
         self.running = true;
+        let mut changed = true;
         while self.running {
-            terminal.draw(|frame| self.render(frame))?;
-            self.handle_crossterm_events()?;
+            if changed {
+                let frame = terminal.draw(|frame| self.render(frame))?;
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.record(frame.buffer)?;
+                }
+            }
+            changed = self.handle_crossterm_events()?;
         }
         Ok(())
     }
@@ -61,7 +142,7 @@ impl App {
             .margin(2)
             .constraints(
                 [
-                    Constraint::Length(1),
+                    Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Length(3),
                     Constraint::Min(0),
@@ -72,13 +153,38 @@ impl App {
 
         let (msg, style) = match self.input_mode {
             InputMode::Normal => (
-                vec![
+                [
                     Span::raw("Press "),
                     Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" to exit, "),
                     Span::styled("e", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" to start editing."),
-                ],
+                    Span::raw(" to start editing, "),
+                    Span::styled("y", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to copy the data, "),
+                    Span::styled("←/→", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to scroll, "),
+                    Span::styled("w", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to show as many bars as fit, "),
+                    Span::styled("d", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to change downsampling, "),
+                    Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to toggle the level readout ("),
+                    Span::styled("↑/↓", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to move it)"),
+                ]
+                .into_iter()
+                .chain(if self.read_only {
+                    vec![Span::raw(".")]
+                } else {
+                    vec![
+                        Span::raw(", "),
+                        Span::styled("n/a", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(" to negate or take the absolute value, "),
+                        Span::styled("s/o", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(" to scale or offset the data."),
+                    ]
+                })
+                .collect(),
                 Style::default().add_modifier(Modifier::RAPID_BLINK),
             ),
             InputMode::Editing => (
@@ -91,29 +197,53 @@ impl App {
                 ],
                 Style::default(),
             ),
+            InputMode::Constant(_) => (
+                vec![
+                    Span::raw("Press "),
+                    Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to cancel, "),
+                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to apply"),
+                ],
+                Style::default(),
+            ),
         };
-        let text = Line::from(msg).patch_style(style);
-        let help_message = Paragraph::new(text);
+        let mut text = Line::from(msg).patch_style(style);
+        if self.read_only {
+            text.spans.insert(
+                0,
+                Span::styled(
+                    " READ-ONLY ",
+                    Style::default().fg(Color::Black).bg(Color::Yellow),
+                ),
+            );
+            text.spans.insert(1, Span::raw(" "));
+        }
+        let help_message = Paragraph::new(text).wrap(Wrap { trim: true });
         frame.render_widget(help_message, chunks[0]);
 
-        let input = Paragraph::new(self.input.as_str())
+        let (title, input) = match self.input_mode {
+            InputMode::Constant(pending) => (pending.prompt(), &self.constant),
+            _ => ("CSV Path", &self.input),
+        };
+        let input_box = Paragraph::new(input.as_str())
             .style(match self.input_mode {
                 InputMode::Normal => Style::default(),
-                InputMode::Editing => Style::default().fg(Color::Yellow),
+                InputMode::Editing | InputMode::Constant(_) => Style::default().fg(Color::Yellow),
             })
-            .block(Block::bordered().title("CSV Path"));
-        frame.render_widget(input, chunks[1]);
+            .block(Block::bordered().title(title));
+        frame.render_widget(input_box, chunks[1]);
 
         match self.input_mode {
             InputMode::Normal =>
                 // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
                 {}
 
-            InputMode::Editing => {
+            InputMode::Editing | InputMode::Constant(_) => {
                 // Make the cursor visible and ask ratatui to put it at the specified coordinates after rendering
                 frame.set_cursor_position((
                     // Put cursor past the end of the input text
-                    chunks[1].x + self.input.len() as u16 + 1,
+                    chunks[1].x + input.len() as u16 + 1,
                     // Move one line down, from the border to the input line
                     chunks[1].y + 1,
                 ));
@@ -122,51 +252,50 @@ impl App {
 
         let error_message = if let Some(err) = &self.error_message {
             Paragraph::new(err.as_str()).style(Style::default().fg(Color::Red))
+        } else if let Some(status) = &self.status_message {
+            Paragraph::new(status.as_str())
         } else {
             Paragraph::new("Enter a CSV path (e.g., test.csv) and press Enter")
         };
         frame.render_widget(error_message, chunks[2]);
 
-        let bar_data: Vec<Bar> = self
-            .data
-            .iter()
-            .enumerate()
-            .map(|(i, (label, value))| {
-                let color = match i % 6 {
-                    0 => Color::Red,
-                    1 => Color::Green,
-                    2 => Color::Yellow,
-                    3 => Color::Blue,
-                    4 => Color::Magenta,
-                    _ => Color::Cyan,
-                };
-                Bar::default()
-                    .value(*value)
-                    .label(Line::from(label.as_str()))
-                    .style(Style::default().fg(color))
-            })
-            .collect();
-
-        let barchart = BarChart::default()
-            .block(
-                Block::bordered().title(Span::styled(
-                    "Data Chart",
-                    Style::default().add_modifier(Modifier::BOLD),
-                )),
-            )
-            .data(BarGroup::default().bars(&bar_data))
-            .bar_width(9)
-            .bar_gap(1);
-        frame.render_widget(barchart, chunks[3]);
+        let mut chart = ChartLayout::new(
+            "Data Chart",
+            &self.data,
+            chunks[3],
+            self.viewport,
+            self.downsample,
+            self.precision,
+        );
+        if let Some(level) = &mut self.readout {
+            // Snap to a whole row of the plot, which may have been resized since the last frame.
+            *level = (*level).clamp(1, chart.plot.height.max(1));
+            chart = chart.with_level(*level);
+        }
+        TerminalRenderer::new(frame).render(&chart);
+        // Keep scrolling within the data, which depends on how many bars fit.
+        self.viewport.scroll_back = chart.hidden_after();
+        self.chart = Some(chart);
     }
 
     /// Reads the crossterm events and updates the state of [`App`].
-    fn handle_crossterm_events(&mut self) -> color_eyre::Result<()> {
-        match event::read()? {
-            Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
-            _ => {}
-        }
-        Ok(())
+    ///
+    /// Returns whether the screen needs redrawing, which it doesn't for events
+    /// such as the mouse moving.
+    fn handle_crossterm_events(&mut self) -> color_eyre::Result<bool> {
+        Ok(match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.on_key_event(key);
+                true
+            }
+            Event::Mouse(mouse) => self.on_mouse_event(mouse),
+            Event::Paste(text) => {
+                self.on_paste_event(&text);
+                true
+            }
+            Event::Resize(..) => true,
+            _ => false,
+        })
     }
 
     /// Handles the key events and updates the state of [`App`].
@@ -179,16 +308,54 @@ impl App {
                 KeyCode::Char('q') => {
                     self.quit();
                 }
+                KeyCode::Left => {
+                    self.viewport.scroll_back += 1;
+                }
+                KeyCode::Right => {
+                    self.viewport.scroll_back = self.viewport.scroll_back.saturating_sub(1);
+                }
+                KeyCode::Char('w') => {
+                    self.viewport.window = None;
+                    self.set_status("Showing as many bars as fit");
+                }
+                KeyCode::Char('r') => {
+                    // Start halfway up the plot, clamped to its height when rendering.
+                    let middle = self.chart.as_ref().map_or(1, |chart| chart.plot.height / 2);
+                    self.readout = match self.readout {
+                        Some(_) => None,
+                        None => Some(middle),
+                    };
+                }
+                KeyCode::Up => {
+                    if let Some(level) = &mut self.readout {
+                        *level = level.saturating_add(1);
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(level) = &mut self.readout {
+                        *level = level.saturating_sub(1);
+                    }
+                }
+                KeyCode::Char('d') => {
+                    self.downsample = Downsample::cycle(self.downsample);
+                    self.set_status(match self.downsample {
+                        Some(method) => format!("Downsampling with {method}"),
+                        None => "Downsampling off".to_string(),
+                    });
+                }
+                KeyCode::Char('n') => self.transform(Transform::Negate),
+                KeyCode::Char('a') => self.transform(Transform::Abs),
+                KeyCode::Char('s') => self.prompt_constant(Pending::Scale),
+                KeyCode::Char('o') => self.prompt_constant(Pending::Offset),
+                KeyCode::Char('y') => match self.copy_data() {
+                    Ok(()) => self.set_status("Copied data to clipboard"),
+                    Err(e) => self.error_message = Some(format!("Error: {}", e)),
+                },
                 _ => {}
             },
             InputMode::Editing => match key.code {
                 KeyCode::Enter => {
-                    if let Err(e) = self.load_csv() {
-                        self.error_message = Some(format!("Error: {}", e));
-                    } else {
-                        self.error_message = None;
-                        self.input_mode = InputMode::Normal;
-                    }
+                    self.load_input();
                 }
                 KeyCode::Char(c) => {
                     self.input.push(c);
@@ -201,17 +368,165 @@ impl App {
                 }
                 _ => {}
             },
+            InputMode::Constant(pending) => match key.code {
+                KeyCode::Enter => match self.constant.trim().parse::<f64>() {
+                    Ok(constant) if constant.is_finite() => {
+                        self.input_mode = InputMode::Normal;
+                        self.transform(pending.with(constant));
+                    }
+                    _ => {
+                        self.error_message =
+                            Some(format!("Error: Invalid number: {}", self.constant));
+                    }
+                },
+                KeyCode::Char(c) => {
+                    self.constant.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.constant.pop();
+                }
+                KeyCode::Esc => {
+                    self.error_message = None;
+                    self.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Asks for the constant of a transform, which is applied once it is entered.
+    fn prompt_constant(&mut self, pending: Pending) {
+        if let Err(e) = self.ensure_writable() {
+            self.error_message = Some(format!("Error: {}", e));
+            return;
+        }
+        self.constant.clear();
+        self.input_mode = InputMode::Constant(pending);
+    }
+
+    /// Applies a transform to every value of the data.
+    fn transform(&mut self, transform: Transform) {
+        if let Err(e) = self.ensure_writable() {
+            self.error_message = Some(format!("Error: {}", e));
+            return;
+        }
+        // Work out every value first, so the data is left as it was if any overflow.
+        let values: Vec<f64> = self
+            .data
+            .iter()
+            .map(|(_, value)| transform.apply(*value))
+            .collect();
+        if values.iter().any(|value| !value.is_finite()) {
+            self.error_message = Some("Error: The result is too large to chart".to_string());
+            return;
+        }
+        for ((_, value), transformed) in self.data.iter_mut().zip(values) {
+            *value = transformed;
+        }
+        self.set_status(transform.to_string());
+    }
+
+    /// Shows `status` in place of any error, which it supersedes.
+    fn set_status(&mut self, status: impl Into<String>) {
+        self.error_message = None;
+        self.status_message = Some(status.into());
+    }
+
+    /// Fails in read-only mode, for features which modify the data.
+    fn ensure_writable(&self) -> color_eyre::Result<()> {
+        if self.read_only {
+            return Err(color_eyre::eyre::eyre!(
+                "Read-only mode, the data can't be changed"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Handles the mouse events and updates the state of [`App`].
+    ///
+    /// Returns whether the event changed anything.
+    fn on_mouse_event(&mut self, mouse: MouseEvent) -> bool {
+        let MouseEventKind::Down(MouseButton::Left) = mouse.kind else {
+            return false;
+        };
+        let Some(chart) = &self.chart else {
+            return false;
+        };
+        let coords = ChartCoords::new(chart);
+        if !coords.contains(Position::new(mouse.column, mouse.row)) {
+            return false;
+        }
+        // Clicking a bar reads out its value, anywhere else reads out the level of the row.
+        let status = match coords.bar_at(mouse.column) {
+            Some(index) => {
+                let bar = &chart.bars[index];
+                format!("{}: {}", bar.label, self.precision.format(bar.value))
+            }
+            None => {
+                let level = coords.row_to_value(mouse.row);
+                format!("Level: {}", self.precision.format(level))
+            }
+        };
+        self.set_status(status);
+        true
+    }
+
+    /// Handles pasted text and updates the state of [`App`].
+    ///
+    /// Dropping a file onto most terminals pastes its path, so pasting an
+    /// absolute path in normal mode loads it straight away.
+    fn on_paste_event(&mut self, text: &str) {
+        match self.input_mode {
+            InputMode::Normal => {
+                let Some(path) = dropped_path(text) else {
+                    return;
+                };
+                self.input = path;
+                self.load_input();
+            }
+            InputMode::Editing => {
+                self.input.extend(text.chars().filter(|c| !c.is_control()));
+            }
+            InputMode::Constant(_) => {
+                self.constant
+                    .extend(text.chars().filter(|c| !c.is_control()));
+            }
+        }
+    }
+
+    /// Loads the CSV at the input path, staying in editing mode on failure.
+    ///
+    /// The input is kept as it is, so a mistyped path only needs correcting
+    /// rather than entering again.
+    fn load_input(&mut self) {
+        if let Err(e) = self.load_csv() {
+            self.error_message = Some(format!("Error: {}", e));
+            self.input_mode = InputMode::Editing;
+        } else {
+            self.error_message = None;
+            self.input_mode = InputMode::Normal;
         }
     }
 
     fn load_csv(&mut self) -> color_eyre::Result<()> {
-        let mut rdr = csv::Reader::from_path(&self.input)?;
+        // Opening a directory succeeds on some platforms and only fails once it is read.
+        if Path::new(&self.input).is_dir() {
+            return Err(color_eyre::eyre::eyre!(
+                "{} is a directory, not a CSV file",
+                self.input
+            ));
+        }
+        let mut rdr = csv::Reader::from_path(&self.input)
+            .map_err(|e| color_eyre::eyre::eyre!("Can't open {}: {}", self.input, e))?;
         let mut new_data = Vec::new();
         for result in rdr.records() {
             let record = result?;
             if record.len() >= 2 {
                 let label = record[0].to_string();
-                let value: u64 = record[1].parse()?;
+                let value: f64 = record[1].parse()?;
+                if !value.is_finite() {
+                    return Err(color_eyre::eyre::eyre!("Invalid value: {}", &record[1]));
+                }
                 new_data.push((label, value));
             }
         }
@@ -219,6 +534,24 @@ impl App {
             return Err(color_eyre::eyre::eyre!("No valid data found in CSV"));
         }
         self.data = new_data;
+        self.viewport = Viewport {
+            window: self.window,
+            scroll_back: 0,
+        };
+        Ok(())
+    }
+
+    /// Copies the chart data to the clipboard as CSV text.
+    fn copy_data(&self) -> color_eyre::Result<()> {
+        if self.data.is_empty() {
+            return Err(color_eyre::eyre::eyre!("No data to copy"));
+        }
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        for (label, value) in &self.data {
+            wtr.write_record([label.as_str(), &value.to_string()])?;
+        }
+        let text = String::from_utf8(wtr.into_inner()?)?;
+        clipboard::copy(&text)?;
         Ok(())
     }
 
@@ -227,3 +560,100 @@ impl App {
         self.running = false;
     }
 }
+
+/// Extracts the path from text pasted by dropping a file onto the terminal.
+///
+/// Terminals quote paths containing special characters, or escape each of
+/// them with a backslash. Returns `None` unless the text is a single absolute
+/// path, which rules out dropping several files at once.
+fn dropped_path(text: &str) -> Option<String> {
+    let text = text.trim();
+    let path = match text.strip_prefix(['\'', '"']) {
+        Some(quoted) => {
+            let quote = &text[..1];
+            let path = quoted.strip_suffix(quote)?;
+            // Several files are dropped as several quoted paths.
+            if path.contains(quote) {
+                return None;
+            }
+            path.to_string()
+        }
+        None => {
+            let mut path = String::with_capacity(text.len());
+            let mut chars = text.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    // Backslashes separate the parts of Windows paths instead.
+                    '\\' if !cfg!(windows) => path.extend(chars.next()),
+                    // Several files are dropped as paths separated by spaces.
+                    c if c.is_whitespace() => return None,
+                    c => path.push(c),
+                }
+            }
+            path
+        }
+    };
+    (!path.contains('\n') && Path::new(&path).is_absolute()).then_some(path)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_path_is_taken_as_is() {
+        assert_eq!(dropped_path("/tmp/data.csv"), Some("/tmp/data.csv".into()));
+        assert_eq!(
+            dropped_path(" /tmp/data.csv\n"),
+            Some("/tmp/data.csv".into())
+        );
+    }
+
+    #[test]
+    fn dropped_path_is_unquoted() {
+        assert_eq!(
+            dropped_path("'/tmp/my data.csv'"),
+            Some("/tmp/my data.csv".into())
+        );
+        assert_eq!(
+            dropped_path("\"/tmp/it's here.csv\""),
+            Some("/tmp/it's here.csv".into())
+        );
+    }
+
+    #[test]
+    fn dropped_path_is_unescaped() {
+        assert_eq!(
+            dropped_path("/tmp/my\\ data.csv"),
+            Some("/tmp/my data.csv".into())
+        );
+        assert_eq!(
+            dropped_path("/tmp/with\\(paren\\).csv"),
+            Some("/tmp/with(paren).csv".into())
+        );
+        assert_eq!(
+            dropped_path("/tmp/Tom\\'s\\ \\&\\ Jerry\\'s.csv"),
+            Some("/tmp/Tom's & Jerry's.csv".into())
+        );
+        assert_eq!(
+            dropped_path("/tmp/back\\\\slash.csv"),
+            Some("/tmp/back\\slash.csv".into())
+        );
+    }
+
+    #[test]
+    fn dropped_path_rejects_several_files() {
+        assert_eq!(dropped_path("'/tmp/a.csv' '/tmp/b.csv'"), None);
+        assert_eq!(dropped_path("\"/tmp/a.csv\" \"/tmp/b.csv\""), None);
+        assert_eq!(dropped_path("/tmp/a.csv /tmp/b.csv"), None);
+        assert_eq!(dropped_path("/tmp/a.csv\n/tmp/b.csv"), None);
+    }
+
+    #[test]
+    fn dropped_path_rejects_other_text() {
+        assert_eq!(dropped_path("hello"), None);
+        assert_eq!(dropped_path("data.csv"), None);
+        assert_eq!(dropped_path("'/tmp/unterminated.csv"), None);
+        assert_eq!(dropped_path(""), None);
+    }
+}
@@ -0,0 +1,202 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::bail;
+
+/// How many digits values are shown with, in labels and readouts alike.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Every digit of the whole part, and up to six significant digits in all.
+    #[default]
+    Auto,
+    /// A fixed number of digits after the decimal point.
+    Decimals(usize),
+    /// A fixed number of significant digits.
+    Significant(usize),
+}
+
+impl Precision {
+    /// The most digits a precision can have, which is all an `f64` holds.
+    const MAX_DIGITS: usize = 17;
+    /// The range of decimal exponents written out in full, outside which values are
+    /// shown in exponent form, such as `1.23e25`.
+    const EXPONENTS: std::ops::RangeInclusive<i32> = -5..=14;
+
+    /// Format `value` with this precision.
+    pub fn format(self, value: f64) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        match self {
+            Self::Auto => {
+                let exponent = Self::exponent(value);
+                let digits = if Self::EXPONENTS.contains(&exponent) {
+                    (exponent + 1).max(6) as usize
+                } else {
+                    6
+                };
+                Self::trim_zeros(&Self::Significant(digits).format(value))
+            }
+            Self::Decimals(decimals) => {
+                let decimals = decimals.min(Self::MAX_DIGITS);
+                if Self::exponent(value) > *Self::EXPONENTS.end() {
+                    format!("{value:.decimals$e}")
+                } else {
+                    format!("{value:.decimals$}")
+                }
+            }
+            Self::Significant(digits) => {
+                let digits = digits.clamp(1, Self::MAX_DIGITS);
+                // Rounding in exponent form is exact, and can carry into a new
+                // leading digit, such as 9.99 becoming 1.00e1.
+                let scientific = format!("{value:.0$e}", digits - 1);
+                let exponent = Self::exponent_of(&scientific);
+                if !Self::EXPONENTS.contains(&exponent) {
+                    return scientific;
+                }
+                let rounded: f64 = scientific.parse().unwrap_or(value);
+                let decimals = (digits as i32 - 1 - exponent).max(0) as usize;
+                format!("{rounded:.decimals$}")
+            }
+        }
+    }
+
+    /// The decimal exponent of a finite `value`, or 0 for 0.
+    fn exponent(value: f64) -> i32 {
+        Self::exponent_of(&format!("{value:e}"))
+    }
+
+    /// The exponent of a value formatted in exponent form.
+    fn exponent_of(scientific: &str) -> i32 {
+        scientific
+            .split_once('e')
+            .and_then(|(_, exponent)| exponent.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Drop trailing zeros after the decimal point, keeping any exponent.
+    fn trim_zeros(formatted: &str) -> String {
+        let (mantissa, exponent) = match formatted.split_once('e') {
+            Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+            None => (formatted, None),
+        };
+        let mantissa = if mantissa.contains('.') {
+            mantissa.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            mantissa
+        };
+        match exponent {
+            Some(exponent) => format!("{mantissa}e{exponent}"),
+            None => mantissa.to_string(),
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(Self::Auto);
+        }
+        let (digits, significant) = match s.strip_suffix('s') {
+            Some(digits) => (digits, true),
+            None => (s, false),
+        };
+        match (digits.parse(), significant) {
+            (Ok(0), true) => bail!("precision needs at least 1 significant digit"),
+            (Ok(digits), _) if digits > Self::MAX_DIGITS => {
+                bail!(
+                    "precision can be at most {} digits, got {s}",
+                    Self::MAX_DIGITS
+                )
+            }
+            (Ok(digits), true) => Ok(Self::Significant(digits)),
+            (Ok(digits), false) => Ok(Self::Decimals(digits)),
+            (Err(_), _) => bail!(
+                "invalid precision {s:?}, expected auto, a number of decimals such as 2, \
+                 or of significant digits such as 3s"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_digits_within_range() {
+        assert_eq!("auto".parse::<Precision>().unwrap(), Precision::Auto);
+        assert_eq!("0".parse::<Precision>().unwrap(), Precision::Decimals(0));
+        assert_eq!("17".parse::<Precision>().unwrap(), Precision::Decimals(17));
+        assert_eq!(
+            "1s".parse::<Precision>().unwrap(),
+            Precision::Significant(1)
+        );
+        assert_eq!(
+            "17s".parse::<Precision>().unwrap(),
+            Precision::Significant(17)
+        );
+    }
+
+    #[test]
+    fn rejects_digits_out_of_range() {
+        for s in ["0s", "18", "18s", "70000", "70000s", "-1", "x"] {
+            assert!(s.parse::<Precision>().is_err(), "{s} was accepted");
+        }
+    }
+
+    #[test]
+    fn auto_keeps_every_integer_digit() {
+        assert_eq!(Precision::Auto.format(0.0), "0");
+        assert_eq!(Precision::Auto.format(42.0), "42");
+        assert_eq!(Precision::Auto.format(-3.5), "-3.5");
+        assert_eq!(Precision::Auto.format(0.1 + 0.2), "0.3");
+        assert_eq!(Precision::Auto.format(1234567.0), "1234567");
+        assert_eq!(Precision::Auto.format(1234567.5), "1234568");
+        assert_eq!(Precision::Auto.format(999999.5), "1000000");
+        assert_eq!(Precision::Auto.format(123456789012345.0), "123456789012345");
+    }
+
+    #[test]
+    fn auto_uses_exponent_form_for_large_and_tiny_values() {
+        assert_eq!(Precision::Auto.format(1.23456e25), "1.23456e25");
+        assert_eq!(Precision::Auto.format(1e15), "1e15");
+        assert_eq!(Precision::Auto.format(f64::MAX), "1.79769e308");
+        assert_eq!(Precision::Auto.format(-1.5e-7), "-1.5e-7");
+        assert_eq!(Precision::Auto.format(5e-324), "4.94066e-324");
+    }
+
+    #[test]
+    fn significant_rounds_to_digits() {
+        assert_eq!(Precision::Significant(3).format(0.0), "0.00");
+        assert_eq!(Precision::Significant(3).format(1.23456), "1.23");
+        assert_eq!(Precision::Significant(3).format(9.999), "10.0");
+        assert_eq!(Precision::Significant(3).format(-123456.0), "-123000");
+        assert_eq!(Precision::Significant(3).format(0.000123456), "0.000123");
+        assert_eq!(Precision::Significant(1).format(0.5), "0.5");
+    }
+
+    #[test]
+    fn significant_uses_exponent_form_for_large_and_tiny_values() {
+        assert_eq!(Precision::Significant(3).format(1.23456e25), "1.23e25");
+        assert_eq!(Precision::Significant(3).format(f64::MAX), "1.80e308");
+        assert_eq!(Precision::Significant(3).format(5e-324), "4.94e-324");
+        assert_eq!(Precision::Significant(2).format(-1.5e-7), "-1.5e-7");
+    }
+
+    #[test]
+    fn decimals_are_fixed() {
+        assert_eq!(Precision::Decimals(2).format(1.23456), "1.23");
+        assert_eq!(Precision::Decimals(0).format(2.5), "2");
+        assert_eq!(Precision::Decimals(2).format(5e-324), "0.00");
+        assert_eq!(Precision::Decimals(2).format(1.23456e25), "1.23e25");
+    }
+
+    #[test]
+    fn non_finite_values_are_named() {
+        assert_eq!(Precision::Auto.format(f64::INFINITY), "inf");
+        assert_eq!(Precision::Significant(3).format(f64::NEG_INFINITY), "-inf");
+        assert_eq!(Precision::Decimals(2).format(f64::NAN), "NaN");
+    }
+}
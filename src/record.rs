@@ -0,0 +1,274 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Records rendered frames to an [asciinema v2] cast file.
+///
+/// Every frame is written as a full redraw of the screen, so the cast can be
+/// replayed with `asciinema play` or uploaded as is.
+///
+/// [asciinema v2]: https://docs.asciinema.org/manual/asciicast/v2/
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    /// When the recording started, used for the event timestamps.
+    start: Instant,
+    /// Whether to use fixed frame timing instead of the wall clock.
+    deterministic: bool,
+    /// The number of frames recorded so far.
+    frames: u32,
+    /// The size of the last recorded frame, `None` until the header is written.
+    size: Option<(u16, u16)>,
+    /// The output of the last recorded frame, to leave out frames which change nothing.
+    last: String,
+}
+
+impl Recorder {
+    /// The time between frames of a deterministic recording.
+    const FRAME_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Create a recorder writing to the file at `path`, truncating it.
+    ///
+    /// A `deterministic` recorder spaces frames [`Self::FRAME_INTERVAL`] apart
+    /// and leaves the start time out of the header, so the same session
+    /// always produces the same cast.
+    pub fn create(path: &Path, deterministic: bool) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+            deterministic,
+            frames: 0,
+            size: None,
+            last: String::new(),
+        })
+    }
+
+    /// Append a rendered frame to the recording, unless it is the same as the last one.
+    ///
+    /// Skipping repeated frames keeps a deterministic recording down to the
+    /// frames the key presses produced, however else the terminal was used.
+    pub fn record(&mut self, buffer: &Buffer) -> io::Result<()> {
+        let size = (buffer.area.width, buffer.area.height);
+        let output = to_ansi(buffer);
+        if self.size == Some(size) && output == self.last {
+            return Ok(());
+        }
+        let time = if self.deterministic {
+            (Self::FRAME_INTERVAL * self.frames).as_secs_f64()
+        } else {
+            self.start.elapsed().as_secs_f64()
+        };
+        self.frames += 1;
+        match self.size {
+            None => self.write_header(size)?,
+            Some(previous) if previous != size => {
+                let resize = format!("{}x{}", size.0, size.1);
+                self.write_event(time, "r", &resize)?;
+            }
+            Some(_) => {}
+        }
+        self.size = Some(size);
+        self.write_event(time, "o", &output)?;
+        self.last = output;
+        // Flush every frame so the cast is usable even if the app is killed.
+        self.writer.flush()
+    }
+
+    fn write_header(&mut self, (width, height): (u16, u16)) -> io::Result<()> {
+        if self.deterministic {
+            return writeln!(
+                self.writer,
+                r#"{{"version": 2, "width": {width}, "height": {height}}}"#
+            );
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        writeln!(
+            self.writer,
+            r#"{{"version": 2, "width": {width}, "height": {height}, "timestamp": {timestamp}}}"#
+        )
+    }
+
+    fn write_event(&mut self, time: f64, code: &str, data: &str) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "[{time:.6}, \"{code}\", {}]",
+            json_string(data)
+        )
+    }
+}
+
+/// Convert a buffer into the escape sequences which redraw it on a terminal.
+fn to_ansi(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut out = String::from("\x1b[0m\x1b[2J");
+    for y in 0..area.height {
+        // Position every row explicitly so a misrendered cell can't shift the rest of the screen.
+        let _ = write!(out, "\x1b[{};1H", y + 1);
+        let mut style = None;
+        // Cells covered by a wide symbol hold a placeholder which must not be written.
+        let mut covered = 0;
+        for x in 0..area.width {
+            if covered > 0 {
+                covered -= 1;
+                continue;
+            }
+            let cell = &buffer[(area.x + x, area.y + y)];
+            let cell_style = (cell.fg, cell.bg, cell.modifier);
+            if style != Some(cell_style) {
+                out.push_str(&sgr(cell.fg, cell.bg, cell.modifier));
+                style = Some(cell_style);
+            }
+            out.push_str(cell.symbol());
+            covered = cell.symbol().width().saturating_sub(1);
+        }
+    }
+    out.push_str("\x1b[0m");
+    out
+}
+
+/// Build the select graphic rendition sequence for a cell style.
+fn sgr(fg: Color, bg: Color, modifier: Modifier) -> String {
+    let mut codes = vec![String::from("0")];
+    for (flag, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::SLOW_BLINK, "5"),
+        (Modifier::RAPID_BLINK, "6"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::HIDDEN, "8"),
+        (Modifier::CROSSED_OUT, "9"),
+    ] {
+        if modifier.contains(flag) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(color_code(fg, false));
+    codes.extend(color_code(bg, true));
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+fn color_code(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let base = match color {
+        Color::Reset => return None,
+        Color::Black => 30,
+        Color::Red => 31,
+        Color::Green => 32,
+        Color::Yellow => 33,
+        Color::Blue => 34,
+        Color::Magenta => 35,
+        Color::Cyan => 36,
+        Color::Gray => 37,
+        Color::DarkGray => 90,
+        Color::LightRed => 91,
+        Color::LightGreen => 92,
+        Color::LightYellow => 93,
+        Color::LightBlue => 94,
+        Color::LightMagenta => 95,
+        Color::LightCyan => 96,
+        Color::White => 97,
+        Color::Indexed(i) => return Some(format!("{};5;{i}", 38 + offset)),
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", 38 + offset)),
+    };
+    Some((base + offset).to_string())
+}
+
+/// Quote a string as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, style::Style};
+
+    use super::*;
+
+    /// The text of each row of `to_ansi` output, without any escape sequences.
+    fn rows(ansi: &str) -> Vec<String> {
+        let mut rows: Vec<String> = Vec::new();
+        let mut chars = ansi.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\x1b' {
+                if let Some(row) = rows.last_mut() {
+                    row.push(c);
+                }
+                continue;
+            }
+            // Skip to the final byte of the sequence, which starts a row if it positions the cursor.
+            chars.next();
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    if c == 'H' {
+                        rows.push(String::new());
+                    }
+                    break;
+                }
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn to_ansi_writes_every_row() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 2));
+        buffer.set_string(0, 0, "ab", Style::default());
+        buffer.set_string(1, 1, "cd", Style::default());
+        assert_eq!(rows(&to_ansi(&buffer)), ["ab  ", " cd "]);
+    }
+
+    #[test]
+    fn to_ansi_skips_cells_covered_by_wide_symbols() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 1));
+        buffer.set_string(0, 0, "写a字", Style::default());
+        // Whatever is left in a covered cell must not push the rest of the row right.
+        buffer[(1, 0)].set_symbol("x");
+        let rows = rows(&to_ansi(&buffer));
+        assert_eq!(rows, ["写a字 "]);
+        assert_eq!(rows[0].width(), 6);
+    }
+
+    #[test]
+    fn to_ansi_styles_cells() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer.set_string(0, 0, "a", Style::default().fg(Color::Red));
+        buffer.set_string(1, 0, "b", Style::default().add_modifier(Modifier::BOLD));
+        let ansi = to_ansi(&buffer);
+        assert!(ansi.contains("\x1b[0;31ma\x1b[0;1mb"), "{ansi:?}");
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\n\x1b"), r#""a\"b\\c\n\u001b""#);
+    }
+}
@@ -0,0 +1,390 @@
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Paragraph},
+};
+
+use crate::{coords::ChartCoords, downsample::Downsample, precision::Precision};
+
+/// The colors given to bars in order, wrapping around.
+const PALETTE: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+/// Draws a [`ChartLayout`] to some output.
+///
+/// The layout has already placed and scaled everything, so a renderer only
+/// has to translate it to its backend.
+pub trait Renderer {
+    /// Draw the chart.
+    fn render(&mut self, chart: &ChartLayout);
+}
+
+/// A single bar of a [`ChartLayout`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarGeometry {
+    pub label: String,
+    pub value: f64,
+    pub color: Color,
+}
+
+/// Which part of the data is shown when it doesn't all fit on screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// The most bars to show at once, or as many as fit if `None`.
+    ///
+    /// Ignored when downsampling.
+    pub window: Option<usize>,
+    /// How many of the most recent bars are scrolled out of view to the right.
+    pub scroll_back: usize,
+}
+
+/// The geometry of a bar chart, computed independently of any backend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartLayout {
+    pub title: String,
+    /// The whole chart, including its border.
+    pub area: Rect,
+    /// The part of the chart the bars are drawn in, excluding the border and labels.
+    pub plot: Rect,
+    /// The bars in view, from left to right.
+    pub bars: Vec<BarGeometry>,
+    /// How the data was reduced to fit, if it was.
+    pub downsampled: Option<Downsample>,
+    /// The index in the data of the first bar in view.
+    pub first: usize,
+    /// The total number of bars in the data, after any downsampling.
+    pub total: usize,
+    /// The number of points in the data before any downsampling.
+    pub points: usize,
+    /// The value at the bottom of the plot, which bars are drawn up from.
+    pub min: f64,
+    /// The value drawn as a full height bar.
+    pub max: f64,
+    /// How values are formatted in labels.
+    pub precision: Precision,
+    /// Explains why the chart looks the way it does when the data is degenerate.
+    pub notice: Option<String>,
+    /// The row of the level readout line and its label, if it is shown.
+    pub level: Option<(u16, String)>,
+    pub bar_width: u16,
+    pub bar_gap: u16,
+}
+
+impl ChartLayout {
+    const BAR_WIDTH: u16 = 9;
+    const BAR_GAP: u16 = 1;
+
+    /// Lay out `data` as a titled bar chart filling `area`.
+    ///
+    /// When not all of the data fits, the bars in view are chosen by the
+    /// `viewport`, counting back from the most recent bar, unless the data is
+    /// reduced to fit with `downsample`.
+    pub fn new(
+        title: impl Into<String>,
+        data: &[(String, f64)],
+        area: Rect,
+        viewport: Viewport,
+        downsample: Option<Downsample>,
+        precision: Precision,
+    ) -> Self {
+        let inner = area.inner(Margin::new(1, 1));
+        // One row under the bars holds the labels, unless there is no room left for the bars.
+        let plot = Rect {
+            height: inner.height - u16::from(inner.height >= 2),
+            ..inner
+        };
+        let capacity = Self::capacity(plot.width);
+        let points = data.len();
+        // Whether the data is degenerate depends on all of it, not on what is left after
+        // downsampling, which may be a single bar.
+        let (min, max, notice) = Self::bounds(data, precision);
+        let sampled;
+        let (data, downsampled) = match downsample {
+            Some(method) if data.len() > capacity => {
+                sampled = method.apply(data, capacity);
+                (sampled.as_slice(), Some(method))
+            }
+            _ => (data, None),
+        };
+        // Downsampling fits the whole series on screen, so the window only applies without it.
+        let len = match (downsample, viewport.window) {
+            (None, Some(window)) => window.min(capacity),
+            _ => capacity,
+        };
+        let first = data
+            .len()
+            .saturating_sub(len)
+            .saturating_sub(viewport.scroll_back);
+        let bars = data
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(len)
+            .map(|(i, (label, value))| BarGeometry {
+                label: label.clone(),
+                value: *value,
+                // Colors follow the data rather than the position, so they stay put while scrolling.
+                color: PALETTE[i % PALETTE.len()],
+            })
+            .collect();
+        Self {
+            title: title.into(),
+            area,
+            plot,
+            bars,
+            downsampled,
+            first,
+            total: data.len(),
+            points,
+            min,
+            max,
+            precision,
+            notice,
+            level: None,
+            bar_width: Self::BAR_WIDTH,
+            bar_gap: Self::BAR_GAP,
+        }
+    }
+
+    /// Show a level readout `level` rows up from the bottom of the plot.
+    ///
+    /// The readout is left out when there are no bars to read it against.
+    pub fn with_level(mut self, level: u16) -> Self {
+        if !self.bars.is_empty() && self.plot.height > 0 {
+            let row = self.plot.bottom().saturating_sub(level);
+            let value = ChartCoords::new(&self).row_to_value(row);
+            self.level = Some((row, self.precision.format(value)));
+        }
+        self
+    }
+
+    /// The number of bars out of view to the left.
+    pub fn hidden_before(&self) -> usize {
+        self.first
+    }
+
+    /// The number of bars out of view to the right.
+    pub fn hidden_after(&self) -> usize {
+        self.total - self.first - self.bars.len()
+    }
+
+    /// The values at the bottom and top of the plot, with a notice if the data is degenerate.
+    ///
+    /// The plot always spans 0, which bars are drawn up or down from. Constant
+    /// data gets headroom, so the bars don't fill the whole chart and read as
+    /// an empty box.
+    fn bounds(data: &[(String, f64)], precision: Precision) -> (f64, f64, Option<String>) {
+        let values = data.iter().map(|(_, value)| *value);
+        let (Some(min), Some(max)) = (
+            values.clone().min_by(f64::total_cmp),
+            values.max_by(f64::total_cmp),
+        ) else {
+            return (0.0, 1.0, None);
+        };
+        if min == 0.0 && max == 0.0 {
+            (0.0, 1.0, Some("All values are 0".to_string()))
+        } else if data.len() == 1 || min == max {
+            let notice = if data.len() == 1 {
+                "Only one data point".to_string()
+            } else {
+                format!("All values are {}", precision.format(max))
+            };
+            if max > 0.0 {
+                (0.0, max * 2.0, Some(notice))
+            } else {
+                (min * 2.0, 0.0, Some(notice))
+            }
+        } else {
+            (min.min(0.0), max.max(0.0), None)
+        }
+    }
+
+    /// The number of bars which fit side by side in `width` cells.
+    fn capacity(width: u16) -> usize {
+        usize::from((width + Self::BAR_GAP) / (Self::BAR_WIDTH + Self::BAR_GAP))
+    }
+}
+
+/// Renders charts to a ratatui [`Frame`].
+pub struct TerminalRenderer<'a, 'b> {
+    frame: &'a mut Frame<'b>,
+}
+
+impl<'a, 'b> TerminalRenderer<'a, 'b> {
+    pub fn new(frame: &'a mut Frame<'b>) -> Self {
+        Self { frame }
+    }
+
+    /// Draw a horizontal line across the plot at `row`, labelled at the right end.
+    ///
+    /// The line only fills empty cells, so the bars it crosses stay readable.
+    fn render_level(&mut self, chart: &ChartLayout, row: u16, label: &str) {
+        let style = Style::default().fg(Color::DarkGray);
+        let buf = self.frame.buffer_mut();
+        for x in chart.plot.left()..chart.plot.right() {
+            let cell = &mut buf[(x, row)];
+            if cell.symbol() == " " {
+                cell.set_symbol("┈").set_style(style);
+            }
+        }
+        let label = format!(" {label} ");
+        let x = chart
+            .plot
+            .right()
+            .saturating_sub(label.len() as u16)
+            .max(chart.plot.left());
+        buf.set_stringn(
+            x,
+            row,
+            label,
+            usize::from(chart.plot.width),
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+    }
+
+    /// Draw the chart's notice, if it has one, centered along the top or bottom of the plot.
+    fn render_notice(&mut self, chart: &ChartLayout) {
+        let Some(notice) = &chart.notice else {
+            return;
+        };
+        let notice = Paragraph::new(notice.as_str())
+            .style(Style::default().fg(Color::DarkGray))
+            .centered();
+        // Bars of data which is all negative hang down from the top, leaving the bottom clear.
+        let y = if chart.max > 0.0 {
+            chart.plot.top()
+        } else {
+            chart.plot.bottom().saturating_sub(1).max(chart.plot.top())
+        };
+        self.frame.render_widget(
+            notice,
+            Rect {
+                y,
+                height: 1,
+                ..chart.plot
+            },
+        );
+    }
+}
+
+impl TerminalRenderer<'_, '_> {
+    /// The bar widget only takes whole numbers, so heights are scaled to this as the maximum.
+    const HEIGHT_SCALE: f64 = 1_000_000.0;
+
+    /// Fill in the bars of negative values, hanging down from `zero`, the row 0 is at.
+    ///
+    /// Like the bar widget, each value is written at the end of its bar when it fits.
+    fn render_negative_bars(&mut self, chart: &ChartLayout, zero: u16) {
+        let rows = f64::from(chart.plot.bottom() - zero);
+        let scale = f64::from(chart.plot.height) / (chart.max - chart.min);
+        let buf = self.frame.buffer_mut();
+        let mut x = chart.plot.left();
+        for bar in &chart.bars {
+            if bar.value < 0.0 {
+                let cells = (-bar.value * scale).round().clamp(1.0, rows) as u16;
+                let style = Style::default().fg(bar.color);
+                for y in zero..zero + cells {
+                    for column in x..x + chart.bar_width {
+                        buf[(column, y)].set_symbol("█").set_style(style);
+                    }
+                }
+                let value = chart.precision.format(bar.value);
+                if value.len() <= usize::from(chart.bar_width) {
+                    let offset = (chart.bar_width - value.len() as u16) / 2;
+                    buf.set_string(x + offset, zero + cells - 1, value, Style::default());
+                }
+            }
+            x += chart.bar_width + chart.bar_gap;
+        }
+    }
+}
+
+impl Renderer for TerminalRenderer<'_, '_> {
+    fn render(&mut self, chart: &ChartLayout) {
+        // The bar widget only draws bars upwards, so it draws the positive bars
+        // above the row 0 is at, and the negative bars are filled in from there down.
+        let zero = if chart.min < 0.0 && chart.plot.height > 0 {
+            ChartCoords::new(chart).value_to_row(0.0)
+        } else {
+            chart.plot.bottom()
+        };
+        let labels: Vec<Bar> = chart
+            .bars
+            .iter()
+            .map(|bar| Bar::default().label(Line::from(bar.label.as_str())))
+            .collect();
+        let bars: Vec<Bar> = chart
+            .bars
+            .iter()
+            .map(|bar| {
+                let height = if chart.max > 0.0 {
+                    (bar.value / chart.max * Self::HEIGHT_SCALE)
+                        .round()
+                        .max(0.0)
+                } else {
+                    0.0
+                };
+                Bar::default()
+                    .value(height as u64)
+                    .text_value(chart.precision.format(bar.value))
+                    .style(Style::default().fg(bar.color))
+            })
+            .collect();
+
+        let mut block = Block::bordered().title(Span::styled(
+            chart.title.as_str(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        if chart.hidden_before() > 0 {
+            block = block.title_bottom(format!("◀ {} more", chart.hidden_before()));
+        }
+        if let Some(method) = chart.downsampled {
+            block = block.title_bottom(
+                Line::from(format!("{method}: {} → {}", chart.points, chart.total)).centered(),
+            );
+        }
+        if chart.hidden_after() > 0 {
+            block = block.title_bottom(
+                Line::from(format!("{} more ▶", chart.hidden_after())).right_aligned(),
+            );
+        }
+
+        let frame = BarChart::default()
+            .block(block)
+            .data(BarGroup::default().bars(&labels))
+            .bar_width(chart.bar_width)
+            .bar_gap(chart.bar_gap);
+        self.frame.render_widget(frame, chart.area);
+        let barchart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(chart.bar_width)
+            .bar_gap(chart.bar_gap)
+            .max(Self::HEIGHT_SCALE as u64);
+        self.frame.render_widget(
+            barchart,
+            Rect {
+                height: zero - chart.plot.top(),
+                ..chart.plot
+            },
+        );
+
+        // Bars drawn down from 0 need the 0 marked to be read correctly.
+        if zero < chart.plot.bottom() && !chart.bars.is_empty() {
+            self.render_negative_bars(chart, zero);
+            self.render_level(chart, zero, &chart.precision.format(0.0));
+        }
+        if let Some((row, label)) = &chart.level {
+            self.render_level(chart, *row, label);
+        }
+        // The notice goes last, so lines drawn across the plot don't run through it.
+        self.render_notice(chart);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// A quick arithmetic adjustment applied to every value of the series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    Negate,
+    Abs,
+    Scale(f64),
+    Offset(f64),
+}
+
+impl Transform {
+    /// Apply the transform to a single value.
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            Self::Negate => -value,
+            Self::Abs => value.abs(),
+            Self::Scale(factor) => value * factor,
+            Self::Offset(offset) => value + offset,
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Negate => write!(f, "Negated the data"),
+            Self::Abs => write!(f, "Took the absolute value of the data"),
+            Self::Scale(factor) => write!(f, "Scaled the data by {factor}"),
+            Self::Offset(offset) => write!(f, "Offset the data by {offset}"),
+        }
+    }
+}
+
+/// A transform waiting for its constant to be entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pending {
+    Scale,
+    Offset,
+}
+
+impl Pending {
+    /// The prompt asking for the constant.
+    pub fn prompt(self) -> &'static str {
+        match self {
+            Self::Scale => "Scale by",
+            Self::Offset => "Offset by",
+        }
+    }
+
+    /// The transform with its constant filled in.
+    pub fn with(self, constant: f64) -> Transform {
+        match self {
+            Self::Scale => Transform::Scale(constant),
+            Self::Offset => Transform::Offset(constant),
+        }
+    }
+}